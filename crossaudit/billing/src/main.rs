@@ -1,27 +1,205 @@
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, RecyclingMethod};
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
 use tokio::time::{sleep, Duration};
-use deadpool_postgres::{Manager, Pool, RecyclingMethod, ManagerConfig};
-use tokio_postgres::{NoTls, Config as PgConfig};
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
+use tokio_postgres::{Config as PgConfig, NoTls, ReadyForQueryStatus, Socket};
+
+use crossaudit_gateway::migrations;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let db_url = std::env::var("DATABASE_URL").unwrap_or_default();
     let pg_cfg: PgConfig = db_url.parse()?;
-    let mgr_config = ManagerConfig { recycling_method: RecyclingMethod::Fast };
-    let mgr = Manager::from_config(pg_cfg, NoTls, mgr_config);
-    let pool = Pool::builder(mgr).max_size(4).build().unwrap();
+    // `backfill` runs several statements per checkout outside of an explicit
+    // transaction; if one errors partway through, `Fast` recycling would
+    // hand the connection straight back out still sitting in whatever
+    // transaction state Postgres left it in. `Verified`'s own test query
+    // only checks the connection still answers at all, not what state it's
+    // in — `backfill` does the actual `ReadyForQuery` transaction-status
+    // inspection itself before the connection would otherwise be recycled.
+    let mgr_config = ManagerConfig { recycling_method: RecyclingMethod::Verified };
+
+    if sslmode_enabled(&db_url) {
+        let connector = MakeTlsConnector::new(TlsConnector::builder().build()?);
+        let mgr = Manager::from_config(pg_cfg, connector, mgr_config);
+        let pool = Pool::builder(mgr).max_size(4).build().unwrap();
+        run(pool).await
+    } else {
+        let mgr = Manager::from_config(pg_cfg, NoTls, mgr_config);
+        let pool = Pool::builder(mgr).max_size(4).build().unwrap();
+        run(pool).await
+    }
+}
+
+/// Whether to encrypt the connection to Postgres: `DATABASE_SSLMODE` wins if
+/// set, otherwise a `sslmode=` parameter on `DATABASE_URL` is used, otherwise
+/// TLS is off so a bare local Postgres keeps working with no extra config.
+///
+/// The billing daemon often runs outside the database's own network, where
+/// the credentials and rollup data would otherwise cross the wire in the
+/// clear, so unlike the gateway's own pool TLS can be switched on here; with
+/// neither setting present it still connects with `NoTls`.
+fn sslmode_enabled(db_url: &str) -> bool {
+    let mode = std::env::var("DATABASE_SSLMODE").ok().or_else(|| {
+        db_url
+            .split('?')
+            .nth(1)
+            .and_then(|qs| qs.split('&').find_map(|kv| kv.strip_prefix("sslmode=")))
+            .map(str::to_string)
+    });
+    !matches!(mode.as_deref(), None | Some("disable"))
+}
+
+async fn run<T>(pool: Pool<T>) -> anyhow::Result<()>
+where
+    T: MakeTlsConnect<Socket> + Clone + Sync + Send + 'static,
+    T::Stream: Sync + Send,
+    T::TlsConnect: Sync + Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    migrations::run(&pool, "./sql").await?;
 
     loop {
-        let client = pool.get().await?;
+        backfill(&pool).await?;
+        sleep(sleep_duration_until_next_midnight()).await;
+    }
+}
+
+/// Rolls up every calendar day between the last recorded `billing_usage`
+/// watermark and today, so a gap left by downtime across a midnight
+/// boundary gets filled in rather than silently skipped.
+async fn backfill<T>(pool: &Pool<T>) -> anyhow::Result<()>
+where
+    T: MakeTlsConnect<Socket> + Clone + Sync + Send + 'static,
+    T::Stream: Sync + Send,
+    T::TlsConnect: Sync + Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    let client = pool.get().await?;
+    let today = Utc::now().date_naive();
+
+    let watermark: Option<NaiveDate> =
+        client.query_opt("SELECT max(ts) FROM billing_usage", &[]).await?.and_then(|row| row.get(0));
+    let earliest_ledger_day: Option<NaiveDate> = client
+        .query_opt("SELECT min(ts_start)::date FROM audit_ledger", &[])
+        .await?
+        .and_then(|row| row.get(0));
+
+    let mut day = match watermark {
+        Some(last) => last + ChronoDuration::days(1),
+        None => earliest_ledger_day.unwrap_or(today),
+    };
+
+    // Prepared once and reused for every day in the backfill window (and
+    // across loop iterations, via deadpool_postgres's statement cache keyed
+    // on the connection) instead of round-tripping a parse/plan per day.
+    let rollup_stmt = client
+        .prepare_cached(
+            "INSERT INTO billing_usage (org_id, ts, tokens) \
+             SELECT org_id, $1, SUM(tokens) \
+             FROM audit_ledger \
+             WHERE ts_start >= $1 AND ts_start < $2 \
+             GROUP BY org_id \
+             ON CONFLICT (org_id, ts) DO UPDATE SET tokens = EXCLUDED.tokens",
+        )
+        .await?;
+
+    let mut result: anyhow::Result<()> = Ok(());
+    while day <= today {
+        let next_day = day + ChronoDuration::days(1);
+        if let Err(err) = client.execute(&rollup_stmt, &[&day, &next_day]).await {
+            result = Err(err.into());
+            break;
+        }
+        day = next_day;
+    }
+
+    // Inspect the backend's `ReadyForQuery` transaction-status byte before
+    // this connection would otherwise go back into the pool: only an idle
+    // (`I`) status means every statement above actually committed cleanly.
+    // Anything else means a failed or still-open transaction, so take the
+    // client out of the pool's bookkeeping and let it drop instead of
+    // recycling it for the next iteration to inherit.
+    if client.transaction_status() != ReadyForQueryStatus::Idle {
+        Object::take(client);
+    }
+    result
+}
+
+/// Sleeps until the next UTC calendar midnight instead of a fixed 86400s,
+/// which would drift by however long each iteration takes to run.
+fn sleep_duration_until_next_midnight() -> Duration {
+    let now = Utc::now();
+    let next_midnight = (now.date_naive() + ChronoDuration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    (next_midnight - now).to_std().unwrap_or(Duration::from_secs(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::NoTls;
+    use uuid::Uuid;
+
+    async fn test_pool() -> Pool {
+        let db_url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "postgresql://localhost/postgres".into());
+        let pg_cfg: PgConfig = db_url.parse().unwrap();
+        let mgr_config = ManagerConfig { recycling_method: RecyclingMethod::Verified };
+        let mgr = Manager::from_config(pg_cfg, NoTls, mgr_config);
+        Pool::builder(mgr).max_size(4).build().unwrap()
+    }
+
+    /// Running `backfill` twice over the same day must not double-count
+    /// tokens: the second run starts the day after the watermark the first
+    /// run wrote, so it leaves today's `billing_usage` row as it was.
+    #[tokio::test]
+    async fn backfill_is_idempotent_across_runs() {
+        let pool = test_pool().await;
+        let client = pool.get().await.unwrap();
+        let org_id = Uuid::new_v4();
+
         client
             .execute(
-                "INSERT INTO billing_usage (org_id, ts, tokens) \
-                 SELECT org_id, CURRENT_DATE, SUM(tokens) \
-                 FROM audit_ledger \
-                 WHERE ts_start >= CURRENT_DATE AND ts_start < CURRENT_DATE + INTERVAL '1 day' \
-                 GROUP BY org_id",
-                &[],
+                "INSERT INTO audit_ledger (org_id, prompt, response, tokens, action, ts_start) \
+                 VALUES ($1, 'p', 'r', 10, 'pass', now())",
+                &[&org_id],
             )
-            .await?;
-        sleep(Duration::from_secs(86400)).await;
+            .await
+            .unwrap();
+
+        backfill(&pool).await.unwrap();
+        let first: i64 = client
+            .query_one("SELECT tokens FROM billing_usage WHERE org_id = $1 AND ts = current_date", &[&org_id])
+            .await
+            .unwrap()
+            .get(0);
+
+        backfill(&pool).await.unwrap();
+        let second: i64 = client
+            .query_one("SELECT tokens FROM billing_usage WHERE org_id = $1 AND ts = current_date", &[&org_id])
+            .await
+            .unwrap()
+            .get(0);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn sslmode_enabled_reads_database_url_query_param() {
+        std::env::remove_var("DATABASE_SSLMODE");
+        assert!(sslmode_enabled("postgresql://localhost/db?sslmode=require"));
+        assert!(!sslmode_enabled("postgresql://localhost/db?sslmode=disable"));
+        assert!(!sslmode_enabled("postgresql://localhost/db"));
+    }
+
+    #[test]
+    fn sslmode_enabled_env_var_overrides_database_url() {
+        std::env::set_var("DATABASE_SSLMODE", "disable");
+        assert!(!sslmode_enabled("postgresql://localhost/db?sslmode=require"));
+        std::env::remove_var("DATABASE_SSLMODE");
     }
 }
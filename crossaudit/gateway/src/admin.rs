@@ -0,0 +1,45 @@
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{keys, AppState};
+
+/// Gatekeeps every `/admin/*` route behind a static bearer token
+/// (`Settings.admin_token`), since these routes read and write provider API
+/// keys and the router has no other auth layer in front of them.
+pub async fn require_admin_token<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> impl IntoResponse {
+    let expected = format!("Bearer {}", state.settings.admin_token);
+    let authorized = !state.settings.admin_token.is_empty()
+        && req
+            .headers()
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v == expected);
+
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "missing or invalid admin token").into_response();
+    }
+    next.run(req).await.into_response()
+}
+
+pub async fn list_keys(State(state): State<AppState>) -> impl IntoResponse {
+    match keys::list(&state.pool).await {
+        Ok(list) => Json(list).into_response(),
+        Err(err) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+pub async fn add_key(State(state): State<AppState>, Json(body): Json<keys::NewKey>) -> impl IntoResponse {
+    match keys::add(&state.pool, body).await {
+        Ok(()) => axum::http::StatusCode::CREATED.into_response(),
+        Err(err) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
@@ -1,13 +1,195 @@
 use anyhow::Result;
+use chrono::{DateTime, SubsecRound, Utc};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
 use crate::AppState;
 
-pub async fn log_chat(state: &AppState, org_id: &str, prompt: &str, response: &str, action: &str, tokens: i32) -> Result<()> {
+const GENESIS_HASH: [u8; 32] = [0u8; 32];
+
+/// Appends one entry to the hash-chained audit ledger. `entry_hash` commits
+/// to the previous row's `entry_hash` for this org plus `org_id`, `prompt`,
+/// `response`, `action`, `tokens`, `fragment_ids` and `ts`, so an edit to
+/// any of those or a deleted row breaks the chain and is caught by
+/// `verify_chain`. `score` and `trace` are stored but not hashed.
+pub async fn log_chat(
+    state: &AppState,
+    org_id: &str,
+    prompt: &str,
+    response: &str,
+    action: &str,
+    tokens: i32,
+    score: Option<f32>,
+    fragments: &[Uuid],
+    trace: Option<&Value>,
+) -> Result<()> {
+    let org_uuid: Uuid = org_id.parse()?;
     let client = state.pool.get().await?;
+
+    let prev_hash: Vec<u8> = client
+        .query_opt(
+            "SELECT entry_hash FROM audit_ledger WHERE org_id = $1 ORDER BY id DESC LIMIT 1",
+            &[&org_uuid],
+        )
+        .await?
+        .map(|row| row.get(0))
+        .unwrap_or_else(|| GENESIS_HASH.to_vec());
+
+    // Postgres keeps TIMESTAMPTZ to the microsecond; hash the same value
+    // `verify_chain` will read back, not the clock's full nanoseconds.
+    let ts = Utc::now().trunc_subsecs(6);
+    // Hash the canonical `Uuid` rendering, not the caller's raw `org_id`
+    // string, so a differently-formatted-but-equal UUID can't produce a
+    // hash `verify_chain` (which always recomputes from the canonical form)
+    // disagrees with.
+    let entry_hash = entry_hash(&prev_hash, &org_uuid.to_string(), prompt, response, action, tokens, fragments, &ts);
+
     client
         .execute(
-            "INSERT INTO audit_ledger (org_id, prompt, response, tokens, action) VALUES ($1,$2,$3,$4,$5)",
-            &[&org_id, &prompt, &response, &tokens, &action],
+            "INSERT INTO audit_ledger \
+                (org_id, prompt, response, tokens, action, score, fragment_ids, trace, prev_hash, entry_hash, ts) \
+             VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)",
+            &[
+                &org_uuid,
+                &prompt,
+                &response,
+                &tokens,
+                &action,
+                &score,
+                &fragments,
+                &trace,
+                &prev_hash,
+                &entry_hash,
+                &ts,
+            ],
         )
         .await?;
     Ok(())
 }
+
+fn entry_hash(
+    prev_hash: &[u8],
+    org_id: &str,
+    prompt: &str,
+    response: &str,
+    action: &str,
+    tokens: i32,
+    fragments: &[Uuid],
+    ts: &DateTime<Utc>,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash);
+    hasher.update(org_id.as_bytes());
+    hasher.update(prompt.as_bytes());
+    hasher.update(response.as_bytes());
+    hasher.update(action.as_bytes());
+    hasher.update(tokens.to_be_bytes());
+    for id in fragments {
+        hasher.update(id.as_bytes());
+    }
+    hasher.update(ts.to_rfc3339().as_bytes());
+    hasher.finalize().to_vec()
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub org_id: Uuid,
+    pub entries_checked: usize,
+    /// The id of the first row whose recorded hash no longer matches, if any.
+    pub broken_at: Option<Uuid>,
+    pub intact: bool,
+}
+
+/// Walks `org_id`'s chain in order, recomputing each `entry_hash` and
+/// checking it against the recorded `prev_hash`/`entry_hash` pair, so a
+/// silently edited or deleted row is detected rather than trusted.
+pub async fn verify_chain(pool: &Pool, org_id: Uuid) -> Result<VerifyReport> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            "SELECT id, prompt, response, action, tokens, fragment_ids, prev_hash, entry_hash, ts \
+             FROM audit_ledger WHERE org_id = $1 ORDER BY id ASC",
+            &[&org_id],
+        )
+        .await?;
+
+    let mut expected_prev = GENESIS_HASH.to_vec();
+    let mut entries_checked = 0usize;
+    for row in &rows {
+        let id: Uuid = row.get(0);
+        let prompt: String = row.get(1);
+        let response: String = row.get(2);
+        let action: String = row.get(3);
+        let tokens: i32 = row.get(4);
+        let fragments: Vec<Uuid> = row.get(5);
+        let prev_hash: Vec<u8> = row.get(6);
+        let recorded_hash: Vec<u8> = row.get(7);
+        let ts: DateTime<Utc> = row.get(8);
+
+        entries_checked += 1;
+        let org_str = org_id.to_string();
+        let recomputed = entry_hash(&prev_hash, &org_str, &prompt, &response, &action, tokens, &fragments, &ts);
+        if prev_hash != expected_prev || recomputed != recorded_hash {
+            return Ok(VerifyReport { org_id, entries_checked, broken_at: Some(id), intact: false });
+        }
+        expected_prev = recorded_hash;
+    }
+    Ok(VerifyReport { org_id, entries_checked, broken_at: None, intact: true })
+}
+
+/// Publishes a Merkle root over every entry added since the last checkpoint
+/// into `ledger_checkpoints`, so a compact root can be externally anchored
+/// without re-walking the whole ledger.
+pub async fn checkpoint(pool: &Pool, org_id: Uuid) -> Result<()> {
+    let client = pool.get().await?;
+    let last_entry_id: i64 = client
+        .query_opt(
+            "SELECT last_entry_id FROM ledger_checkpoints WHERE org_id = $1 ORDER BY id DESC LIMIT 1",
+            &[&org_id],
+        )
+        .await?
+        .map(|row| row.get(0))
+        .unwrap_or(0);
+
+    let rows = client
+        .query(
+            "SELECT id, entry_hash FROM audit_ledger WHERE org_id = $1 AND id > $2 ORDER BY id ASC",
+            &[&org_id, &last_entry_id],
+        )
+        .await?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let leaves: Vec<Vec<u8>> = rows.iter().map(|row| row.get(1)).collect();
+    let new_last_entry_id: i64 = rows.last().unwrap().get(0);
+    let root = merkle_root(&leaves);
+
+    client
+        .execute(
+            "INSERT INTO ledger_checkpoints (org_id, last_entry_id, root_hash, created_at) VALUES ($1,$2,$3,now())",
+            &[&org_id, &new_last_entry_id, &root],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Folds leaf hashes into a single root by repeatedly hashing adjacent
+/// pairs, duplicating the final node when a layer has an odd count.
+fn merkle_root(leaves: &[Vec<u8>]) -> Vec<u8> {
+    let mut layer = leaves.to_vec();
+    while layer.len() > 1 {
+        let mut next = Vec::with_capacity((layer.len() + 1) / 2);
+        for pair in layer.chunks(2) {
+            let mut hasher = Sha256::new();
+            hasher.update(&pair[0]);
+            hasher.update(pair.get(1).unwrap_or(&pair[0]));
+            next.push(hasher.finalize().to_vec());
+        }
+        layer = next;
+    }
+    layer.into_iter().next().unwrap_or_else(|| GENESIS_HASH.to_vec())
+}
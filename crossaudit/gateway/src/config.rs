@@ -5,8 +5,24 @@ pub struct Settings {
     pub server_addr: String,
     pub database_url: String,
     pub openai_api_key: String,
+    /// Bearer token required on `/admin/*` routes. Empty means no token has
+    /// been provisioned, in which case those routes reject every request.
+    pub admin_token: String,
     pub storage_path: String,
     pub local_model_path: Option<String>,
+    pub s3_endpoint: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<String>,
+    /// One of "l2", "cosine", "inner_product" — selects the pgvector
+    /// distance operator `data_room::search` orders by.
+    pub vector_distance_metric: String,
+    pub hnsw_ef_search: Option<i32>,
+    pub ivfflat_probes: Option<i32>,
+    /// Drops search results whose normalized similarity (`1.0 - distance`)
+    /// falls below this cutoff.
+    pub vector_min_score: Option<f64>,
 }
 
 impl Settings {
@@ -15,8 +31,18 @@ impl Settings {
             server_addr: env::var("SERVER_ADDR").unwrap_or_else(|_| "0.0.0.0:8000".into()),
             database_url: env::var("DATABASE_URL").unwrap_or_default(),
             openai_api_key: env::var("OPENAI_API_KEY").unwrap_or_default(),
+            admin_token: env::var("ADMIN_TOKEN").unwrap_or_default(),
             storage_path: env::var("STORAGE_PATH").unwrap_or_else(|_| "./storage".into()),
             local_model_path: env::var("LOCAL_MODEL_PATH").ok(),
+            s3_endpoint: env::var("S3_ENDPOINT").ok(),
+            s3_bucket: env::var("S3_BUCKET").ok(),
+            s3_region: env::var("S3_REGION").ok(),
+            s3_access_key: env::var("S3_ACCESS_KEY").ok(),
+            s3_secret_key: env::var("S3_SECRET_KEY").ok(),
+            vector_distance_metric: env::var("VECTOR_DISTANCE_METRIC").unwrap_or_else(|_| "l2".into()),
+            hnsw_ef_search: env::var("HNSW_EF_SEARCH").ok().and_then(|v| v.parse().ok()),
+            ivfflat_probes: env::var("IVFFLAT_PROBES").ok().and_then(|v| v.parse().ok()),
+            vector_min_score: env::var("VECTOR_MIN_SCORE").ok().and_then(|v| v.parse().ok()),
         }
     }
 }
@@ -1,12 +1,23 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use axum::body::Bytes;
 use pgvector::Vector;
 use tokio_postgres::types::ToSql;
+use url::Url;
 use uuid::Uuid;
 
 use crossaudit_ingestor::{chunks, embed, pdf};
 
-use crate::{storage::Storage, AppState};
+use crate::AppState;
+
+/// Where a requested document lives: either redirect the caller straight to
+/// the storage backend, or fall back to streaming the bytes through the
+/// gateway when the backend has no direct-fetch URL to offer.
+pub enum DocLocation {
+    Redirect(Url),
+    Bytes(Vec<u8>),
+}
 
 pub async fn save_doc(state: &AppState, bytes: Bytes) -> Result<()> {
     let path = state.storage.save(&bytes).await?;
@@ -63,15 +74,54 @@ pub async fn list_docs(state: &AppState) -> Result<Vec<String>> {
     state.storage.list().await
 }
 
-pub async fn search(state: &AppState, query: &str, limit: i64) -> Result<Vec<Uuid>> {
+pub async fn get_doc(state: &AppState, id: &str) -> Result<DocLocation> {
+    if let Some(url) = state.storage.presign_get(id, Duration::from_secs(300)).await? {
+        return Ok(DocLocation::Redirect(url));
+    }
+    Ok(DocLocation::Bytes(state.storage.get(id).await?))
+}
+
+/// Each result's `doc_id`/chunk text alongside a relevance score of
+/// `1.0 - distance` (from the distance pgvector reported), so callers can
+/// thread a real relevance score downstream instead of the `None`
+/// placeholder the audit ledger used to record.
+pub async fn search(state: &AppState, query: &str, limit: i64) -> Result<Vec<(Uuid, String, f64)>> {
     let embedding = embed::embed_chunks(&[query.to_string()])?.remove(0);
     let vec: Vector = embedding.into();
-    let client = state.pool.get().await?;
-    let rows = client
-        .query(
-            "SELECT doc_id FROM chunks ORDER BY embedding <-> $1 LIMIT $2",
-            &[&vec, &limit],
-        )
-        .await?;
-    Ok(rows.iter().map(|r| r.get(0)).collect())
+    let operator = match state.settings.vector_distance_metric.as_str() {
+        "cosine" => "<=>",
+        "inner_product" => "<#>",
+        _ => "<->",
+    };
+
+    let mut client = state.pool.get().await?;
+    let tx = client.transaction().await?;
+    if let Some(ef_search) = state.settings.hnsw_ef_search {
+        tx.batch_execute(&format!("SET LOCAL hnsw.ef_search = {ef_search}")).await?;
+    }
+    if let Some(probes) = state.settings.ivfflat_probes {
+        tx.batch_execute(&format!("SET LOCAL ivfflat.probes = {probes}")).await?;
+    }
+
+    let sql = format!(
+        "SELECT doc_id, plaintext, embedding {operator} $1 AS distance \
+         FROM chunks ORDER BY embedding {operator} $1 LIMIT $2"
+    );
+    let rows = tx.query(&sql, &[&vec, &limit]).await?;
+    tx.commit().await?;
+
+    let min_score = state.settings.vector_min_score;
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let distance: f64 = row.get(2);
+            // Distance isn't bounded to [0, 1] for every metric; treat this
+            // as a normalized proxy rather than a true cosine similarity.
+            let score = 1.0 - distance;
+            if min_score.is_some_and(|min| score < min) {
+                return None;
+            }
+            Some((row.get(0), row.get(1), score))
+        })
+        .collect())
 }
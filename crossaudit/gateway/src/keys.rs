@@ -0,0 +1,76 @@
+use anyhow::Result;
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An `evaluator_keys` row as returned to admins. `api_key` is masked down
+/// to its last 4 characters — the admin UI never needs the live secret back,
+/// only enough to tell rows apart.
+#[derive(Serialize)]
+pub struct KeyInfo {
+    pub id: Uuid,
+    pub provider: String,
+    pub api_key: String,
+}
+
+/// Masks all but the last 4 characters of a secret, e.g. `sk-abcd1234` -> `*******1234`.
+fn mask(key: &str) -> String {
+    let visible = 4.min(key.len());
+    let (masked, tail) = key.split_at(key.len() - visible);
+    format!("{}{}", "*".repeat(masked.len()), tail)
+}
+
+#[derive(Deserialize)]
+pub struct NewKey {
+    pub provider: String,
+    pub api_key: String,
+    pub org_id: Option<Uuid>,
+}
+
+pub async fn list(pool: &Pool) -> Result<Vec<KeyInfo>> {
+    let client = pool.get().await?;
+    let rows = client.query("SELECT id, provider, api_key FROM evaluator_keys", &[]).await?;
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let api_key: String = r.get(2);
+            KeyInfo { id: r.get(0), provider: r.get(1), api_key: mask(&api_key) }
+        })
+        .collect())
+}
+
+pub async fn add(pool: &Pool, new_key: NewKey) -> Result<()> {
+    let client = pool.get().await?;
+    client
+        .execute(
+            "INSERT INTO evaluator_keys (org_id, provider, api_key) VALUES ($1,$2,$3)",
+            &[&new_key.org_id.unwrap_or_else(Uuid::nil), &new_key.provider, &new_key.api_key],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Looks up the provider/key pair `org_id` should use, falling back to the
+/// org-less default row (`org_id = nil`) when no org-specific key exists.
+pub async fn active_for_org(pool: &Pool, org_id: Uuid) -> Result<Option<(String, String)>> {
+    let client = pool.get().await?;
+    let row = client
+        .query_opt(
+            "SELECT provider, api_key FROM evaluator_keys WHERE org_id = $1 ORDER BY id DESC LIMIT 1",
+            &[&org_id],
+        )
+        .await?;
+    let row = match row {
+        Some(row) => Some(row),
+        None if org_id != Uuid::nil() => {
+            client
+                .query_opt(
+                    "SELECT provider, api_key FROM evaluator_keys WHERE org_id = $1 ORDER BY id DESC LIMIT 1",
+                    &[&Uuid::nil()],
+                )
+                .await?
+        }
+        None => None,
+    };
+    Ok(row.map(|r| (r.get(0), r.get(1))))
+}
@@ -1,7 +1,22 @@
+pub mod admin;
+pub mod audit;
+pub mod config;
+pub mod data_room;
+pub mod keys;
+pub mod llm_client;
+pub mod metrics;
+pub mod migrations;
+pub mod policy;
+pub mod providers;
+pub mod routes;
+pub mod storage;
+
+use std::time::Instant;
+
+use axum::{extract::MatchedPath, http::Request, middleware::{self, Next}, response::IntoResponse};
 use crate::{config::Settings, policy::PolicyEngine, storage::Storage};
 use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
 use tokio_postgres::{NoTls, Config as PgConfig};
-use tokio::fs;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -18,9 +33,9 @@ pub async fn init_state(settings: Settings) -> anyhow::Result<AppState> {
     let pool = Pool::builder(mgr).max_size(16).build().unwrap();
 
     let policy = PolicyEngine::load_default()?;
-    let storage = Storage::new(&settings.storage_path).await?;
+    let storage = Storage::new(&settings).await?;
 
-    run_migrations(&pool).await?;
+    migrations::run(&pool, "./sql").await?;
 
     Ok(AppState { settings, pool, policy, storage })
 }
@@ -30,20 +45,33 @@ pub fn build_router(state: AppState) -> axum::Router {
     axum::Router::new()
         .route("/chat", post(crate::routes::chat))
         .route("/docs", get(crate::routes::list_docs))
+        .route("/docs/:id", get(crate::routes::get_doc))
         .route("/upload", post(crate::routes::upload_doc))
+        .route("/metrics", get(crate::routes::metrics))
+        .route(
+            "/admin/keys",
+            get(crate::admin::list_keys)
+                .post(crate::admin::add_key)
+                .route_layer(middleware::from_fn_with_state(state.clone(), crate::admin::require_admin_token)),
+        )
+        .layer(middleware::from_fn(track_metrics))
         .with_state(state)
 }
 
-async fn run_migrations(pool: &Pool) -> anyhow::Result<()> {
-    let client = pool.get().await?;
-    let tx = client.transaction().await?;
-    for entry in fs::read_dir("./sql").await? {
-        let entry = entry?;
-        if entry.file_type().await?.is_file() {
-            let sql = fs::read_to_string(entry.path()).await?;
-            tx.batch_execute(&sql).await?;
-        }
-    }
-    tx.commit().await?;
-    Ok(())
+/// Records a request/response pair into `metrics::REQUEST_COUNTER` and
+/// `metrics::RESPONSE_TIME`, labeled by method, path, and status. `path` is
+/// the matched route template (e.g. `/docs/:id`), not the literal URI —
+/// labeling by raw path would mint a new time series per document id.
+async fn track_metrics<B>(matched_path: Option<MatchedPath>, req: Request<B>, next: Next<B>) -> impl IntoResponse {
+    let method = req.method().to_string();
+    let path = matched_path.map(|p| p.as_str().to_string()).unwrap_or_else(|| req.uri().path().to_string());
+    let start = Instant::now();
+
+    let response = next.run(req).await;
+
+    let labels = metrics::RequestLabels { method, path, status: response.status().as_u16() };
+    metrics::REQUEST_COUNTER.get_or_create(&labels).inc();
+    metrics::RESPONSE_TIME.get_or_create(&labels).observe(start.elapsed().as_secs_f64());
+
+    response
 }
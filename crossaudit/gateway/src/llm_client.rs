@@ -1,60 +1,65 @@
-use anyhow::Result;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Result};
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{keys, providers, providers::Message, AppState};
 
-#[derive(Serialize)]
-struct OpenAiRequest<'a> {
-    model: &'a str,
-    messages: Vec<Message<'a>>,
-}
-
-#[derive(Serialize)]
-struct Message<'a> {
-    role: &'a str,
-    content: &'a str,
-}
-
-#[derive(Deserialize)]
-struct Choice {
-    message: MessageResp,
-}
-
-#[derive(Deserialize)]
-struct MessageResp {
-    content: String,
-}
-
-#[derive(Deserialize)]
-struct OpenAiResponse {
-    choices: Vec<Choice>,
-}
-
-pub async fn chat(state: &AppState, prompt: &str, fragments: &[Uuid]) -> Result<String> {
+/// Completes a chat prompt, routing to whichever provider is active for
+/// `org_id` in `evaluator_keys` (falling back to `Settings.openai_api_key`
+/// when no key has been provisioned yet). `provider`/`model` let a caller
+/// pin a specific backend instead of using the org's configured default.
+pub async fn chat(
+    state: &AppState,
+    org_id: Uuid,
+    prompt: &str,
+    fragments: &[Uuid],
+    provider: Option<&str>,
+    model: Option<&str>,
+) -> Result<String> {
     let ctx = if fragments.is_empty() {
         String::new()
     } else {
         format!("Context docs: {:?}\n", fragments)
     };
     let full_prompt = format!("{}{}", ctx, prompt);
-    if !state.settings.openai_api_key.is_empty() {
-        let client = Client::new();
-        let req = OpenAiRequest {
-            model: "gpt-3.5-turbo",
-            messages: vec![Message { role: "user", content: &full_prompt }],
-        };
-        let resp: OpenAiResponse = client
-            .post("https://api.openai.com/v1/chat/completions")
-            .bearer_auth(&state.settings.openai_api_key)
-            .json(&req)
-            .send()
+    let messages = vec![Message { role: "user".to_string(), content: full_prompt }];
+
+    let (provider_name, api_key) = resolve_provider(state, org_id, provider).await?;
+    let model = model.map(str::to_string).unwrap_or_else(|| providers::default_model(&provider_name).to_string());
+
+    let backend = providers::build(&provider_name, &api_key, state.settings.local_model_path.as_deref())?;
+    let content = backend.complete(&model, &messages).await?;
+    record_tokens(&provider_name, content.len() as u64);
+    Ok(content)
+}
+
+/// Picks the provider/key pair to use: an explicit `pinned` override first,
+/// then the org's row in `evaluator_keys`, then the legacy
+/// `Settings.openai_api_key`, then the local stub as a last resort.
+async fn resolve_provider(state: &AppState, org_id: Uuid, pinned: Option<&str>) -> Result<(String, String)> {
+    if let Some(name) = pinned {
+        // A pinned provider with no matching `evaluator_keys` row must fail
+        // rather than fall back to `openai_api_key` — that key belongs to a
+        // different provider and would get paired with the wrong backend.
+        let api_key = keys::active_for_org(&state.pool, org_id)
             .await?
-            .json()
-            .await?;
-        Ok(resp.choices.first().map(|c| c.message.content.clone()).unwrap_or_default())
-    } else {
-        Ok(format!("local model response to '{}'", prompt))
+            .filter(|(provider, _)| provider == name)
+            .map(|(_, key)| key);
+        return match api_key {
+            Some(key) => Ok((name.to_string(), key)),
+            None => bail!("no key configured for provider '{}'", name),
+        };
     }
+    if let Some((name, key)) = keys::active_for_org(&state.pool, org_id).await? {
+        return Ok((name, key));
+    }
+    if !state.settings.openai_api_key.is_empty() {
+        return Ok(("openai".to_string(), state.settings.openai_api_key.clone()));
+    }
+    Ok(("local".to_string(), String::new()))
+}
+
+fn record_tokens(provider: &str, tokens: u64) {
+    crate::metrics::PROVIDER_TOKENS
+        .get_or_create(&crate::metrics::ProviderLabels { provider: provider.to_string() })
+        .inc_by(tokens);
 }
@@ -0,0 +1,78 @@
+use once_cell::sync::Lazy;
+use prometheus_client::encoding::{EncodeLabelSet, text::encode};
+use prometheus_client::metrics::{counter::Counter, family::Family, histogram::Histogram};
+use prometheus_client::registry::Registry;
+
+/// Labels for `gateway_requests` / `gateway_request_duration_seconds`.
+#[derive(Clone, Eq, Hash, PartialEq, Debug, EncodeLabelSet)]
+pub struct RequestLabels {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+}
+
+/// Policy action taken for a request: `pass`, `rewrite`, or `block`.
+#[derive(Clone, Eq, Hash, PartialEq, Debug, EncodeLabelSet)]
+pub struct PolicyActionLabels {
+    pub action: String,
+}
+
+/// Tokens consumed per upstream LLM provider.
+#[derive(Clone, Eq, Hash, PartialEq, Debug, EncodeLabelSet)]
+pub struct ProviderLabels {
+    pub provider: String,
+}
+
+pub static REQUEST_COUNTER: Lazy<Family<RequestLabels, Counter>> = Lazy::new(Family::default);
+
+// LLM calls run tens of seconds; the original 0.1s-5s buckets bottomed out
+// long before a real completion finished.
+pub static RESPONSE_TIME: Lazy<Family<RequestLabels, Histogram>> = Lazy::new(|| {
+    Family::new_with_constructor(|| {
+        Histogram::new([0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0].into_iter())
+    })
+});
+
+pub static POLICY_ACTIONS: Lazy<Family<PolicyActionLabels, Counter>> = Lazy::new(Family::default);
+
+pub static PROVIDER_TOKENS: Lazy<Family<ProviderLabels, Counter>> = Lazy::new(Family::default);
+
+pub static VECTOR_SEARCH_LATENCY: Lazy<Histogram> =
+    Lazy::new(|| Histogram::new([0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0].into_iter()));
+
+pub fn register(registry: &mut Registry) {
+    registry.register("gateway_requests", "Number of gateway HTTP requests", REQUEST_COUNTER.clone());
+    registry.register(
+        "gateway_request_duration_seconds",
+        "Gateway request handler latency",
+        RESPONSE_TIME.clone(),
+    );
+    registry.register(
+        "gateway_policy_actions",
+        "Policy engine actions taken per request",
+        POLICY_ACTIONS.clone(),
+    );
+    registry.register(
+        "gateway_provider_tokens",
+        "Tokens consumed per LLM provider",
+        PROVIDER_TOKENS.clone(),
+    );
+    registry.register(
+        "gateway_vector_search_duration_seconds",
+        "pgvector search latency",
+        VECTOR_SEARCH_LATENCY.clone(),
+    );
+}
+
+static REGISTRY: Lazy<Registry> = Lazy::new(|| {
+    let mut registry = Registry::default();
+    register(&mut registry);
+    registry
+});
+
+/// Renders every registered metric in Prometheus text exposition format.
+pub fn encode_text() -> String {
+    let mut buf = String::new();
+    encode(&mut buf, &REGISTRY).ok();
+    buf
+}
@@ -0,0 +1,118 @@
+use anyhow::{bail, Result};
+use deadpool_postgres::Pool;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tokio::fs;
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
+use tokio_postgres::Socket;
+
+/// Applies every `<dir>/NNNN_name.sql` migration not yet recorded in
+/// `schema_migrations`, in lexicographic (numeric-prefix) order, one
+/// transaction per migration.
+///
+/// Already-applied versions are skipped, but if the checksum recorded for a
+/// version no longer matches the file on disk the migration is aborted
+/// instead of silently re-applying drifted DDL.
+///
+/// Generic over the TLS connector so callers that need an encrypted
+/// connection (see `crossaudit-billing`) can pass a `Pool` built with
+/// something other than `NoTls`.
+pub async fn run<T>(pool: &Pool<T>, dir: &str) -> Result<()>
+where
+    T: MakeTlsConnect<Socket> + Clone + Sync + Send + 'static,
+    T::Stream: Sync + Send,
+    T::TlsConnect: Sync + Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    let mut client = pool.get().await?;
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version TEXT PRIMARY KEY,
+                checksum TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+        )
+        .await?;
+
+    let mut files = discover(dir).await?;
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (version, path) in files {
+        let sql = fs::read_to_string(&path).await?;
+        let checksum = checksum_of(&sql);
+
+        let tx = client.transaction().await?;
+        let existing = tx
+            .query_opt(
+                "SELECT checksum FROM schema_migrations WHERE version = $1",
+                &[&version],
+            )
+            .await?;
+        match existing {
+            Some(row) => {
+                let recorded: String = row.get(0);
+                if recorded != checksum {
+                    bail!(
+                        "migration {} has drifted: recorded checksum {} does not match {} on disk",
+                        version,
+                        recorded,
+                        checksum
+                    );
+                }
+            }
+            None => {
+                tx.batch_execute(&sql).await?;
+                tx.execute(
+                    "INSERT INTO schema_migrations (version, checksum) VALUES ($1, $2)",
+                    &[&version, &checksum],
+                )
+                .await?;
+            }
+        }
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+/// Returns the most recently applied migration version, if any have run.
+pub async fn current_version<T>(pool: &Pool<T>) -> Result<Option<String>>
+where
+    T: MakeTlsConnect<Socket> + Clone + Sync + Send + 'static,
+    T::Stream: Sync + Send,
+    T::TlsConnect: Sync + Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    let client = pool.get().await?;
+    let row = client
+        .query_opt(
+            "SELECT version FROM schema_migrations ORDER BY version DESC LIMIT 1",
+            &[],
+        )
+        .await?;
+    Ok(row.map(|r| r.get(0)))
+}
+
+/// Collects `(version, path)` pairs for every `*.sql` file in `dir`, keyed by
+/// the filename stem (e.g. `0001_init`) so application order is deterministic.
+async fn discover(dir: &str) -> Result<Vec<(String, PathBuf)>> {
+    let mut out = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if !entry.file_type().await?.is_file() {
+            continue;
+        }
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("sql") {
+            continue;
+        }
+        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+            out.push((stem.to_string(), path));
+        }
+    }
+    Ok(out)
+}
+
+fn checksum_of(sql: &str) -> String {
+    hex::encode(Sha256::digest(sql.as_bytes()))
+}
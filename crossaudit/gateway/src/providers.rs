@@ -0,0 +1,153 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// A chat message in the minimal role/content shape shared by every
+/// provider we talk to.
+#[derive(Clone, Serialize)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+/// A backend capable of completing a chat conversation.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    async fn complete(&self, model: &str, messages: &[Message]) -> Result<String>;
+}
+
+/// Instantiates the `Provider` matching a name stored in `evaluator_keys`
+/// (or `Settings.local_model_path` for the `local` fallback).
+pub fn build(name: &str, api_key: &str, local_model_path: Option<&str>) -> Result<Box<dyn Provider>> {
+    match name {
+        "openai" => Ok(Box::new(OpenAiProvider::new(api_key.to_string()))),
+        "anthropic" => Ok(Box::new(AnthropicProvider::new(api_key.to_string()))),
+        "local" => Ok(Box::new(LocalProvider::new(local_model_path.unwrap_or_default().to_string()))),
+        other => bail!("unknown LLM provider '{}'", other),
+    }
+}
+
+/// The default model name to use for a provider when the caller doesn't
+/// pin one explicitly.
+pub fn default_model(provider: &str) -> &'static str {
+    match provider {
+        "anthropic" => "claude-3-haiku-20240307",
+        "local" => "local",
+        _ => "gpt-3.5-turbo",
+    }
+}
+
+pub struct OpenAiProvider {
+    api_key: String,
+    client: Client,
+}
+
+impl OpenAiProvider {
+    pub fn new(api_key: String) -> Self {
+        Self { api_key, client: Client::new() }
+    }
+}
+
+#[derive(Serialize)]
+struct OpenAiRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+}
+
+#[derive(Deserialize)]
+struct OpenAiChoice {
+    message: OpenAiMessage,
+}
+
+#[derive(Deserialize)]
+struct OpenAiMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct OpenAiResponse {
+    choices: Vec<OpenAiChoice>,
+}
+
+#[async_trait]
+impl Provider for OpenAiProvider {
+    async fn complete(&self, model: &str, messages: &[Message]) -> Result<String> {
+        let resp: OpenAiResponse = self
+            .client
+            .post("https://api.openai.com/v1/chat/completions")
+            .bearer_auth(&self.api_key)
+            .json(&OpenAiRequest { model, messages })
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(resp.choices.into_iter().next().map(|c| c.message.content).unwrap_or_default())
+    }
+}
+
+pub struct AnthropicProvider {
+    api_key: String,
+    client: Client,
+}
+
+impl AnthropicProvider {
+    pub fn new(api_key: String) -> Self {
+        Self { api_key, client: Client::new() }
+    }
+}
+
+#[derive(Serialize)]
+struct AnthropicRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    messages: &'a [Message],
+}
+
+#[derive(Deserialize)]
+struct AnthropicContent {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
+}
+
+#[async_trait]
+impl Provider for AnthropicProvider {
+    async fn complete(&self, model: &str, messages: &[Message]) -> Result<String> {
+        let resp: AnthropicResponse = self
+            .client
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&AnthropicRequest { model, max_tokens: 1024, messages })
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(resp.content.into_iter().next().map(|c| c.text).unwrap_or_default())
+    }
+}
+
+/// Talks to a locally hosted model. Until a real local runtime is wired in
+/// this just echoes the prompt, mirroring the stub the gateway used before
+/// provider routing existed.
+pub struct LocalProvider {
+    model_path: String,
+}
+
+impl LocalProvider {
+    pub fn new(model_path: String) -> Self {
+        Self { model_path }
+    }
+}
+
+#[async_trait]
+impl Provider for LocalProvider {
+    async fn complete(&self, _model: &str, messages: &[Message]) -> Result<String> {
+        let prompt = messages.last().map(|m| m.content.as_str()).unwrap_or_default();
+        Ok(format!("local model ({}) response to '{}'", self.model_path, prompt))
+    }
+}
@@ -8,6 +8,14 @@ use serde_json::json;
 #[derive(Deserialize)]
 pub struct ChatRequest {
     pub prompt: String,
+    /// Selects whose `evaluator_keys` row to route through; omit to use the
+    /// org-less default row (mirrors `keys::NewKey.org_id`).
+    pub org_id: Option<Uuid>,
+    /// Pins the LLM provider (e.g. "openai", "anthropic", "local") instead
+    /// of using the org's default from `evaluator_keys`.
+    pub provider: Option<String>,
+    /// Pins the model name passed to the chosen provider.
+    pub model: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -17,14 +25,24 @@ pub struct ChatResponse {
 
 pub async fn chat(State(state): State<AppState>, Json(body): Json<ChatRequest>) -> impl IntoResponse {
     // search relevant docs
+    let search_start = std::time::Instant::now();
     let fragments = match data_room::search(&state, &body.prompt, 3).await {
         Ok(list) => list,
         Err(_) => Vec::new(),
     };
+    crate::metrics::VECTOR_SEARCH_LATENCY.observe(search_start.elapsed().as_secs_f64());
+
     let (mut rewritten, action) = state.policy.apply(&body.prompt);
+    let policy_label = action.clone().unwrap_or_else(|| "pass".into());
+    crate::metrics::POLICY_ACTIONS
+        .get_or_create(&crate::metrics::PolicyActionLabels { action: policy_label })
+        .inc();
+    let top_score = fragments.first().map(|(_, _, score)| *score as f32);
+    let org_id = body.org_id.unwrap_or_else(Uuid::nil);
+    let org_id_str = org_id.to_string();
     if action.as_deref() == Some("block") {
-        let ids: Vec<Uuid> = fragments.iter().map(|(id, _)| *id).collect();
-        audit::log_chat(&state, "00000000-0000-0000-0000-000000000000", &body.prompt, "blocked", "block", 0, None, &ids, None).await.ok();
+        let ids: Vec<Uuid> = fragments.iter().map(|(id, _, _)| *id).collect();
+        audit::log_chat(&state, &org_id_str, &body.prompt, "blocked", "block", 0, top_score, &ids, None).await.ok();
         return (axum::http::StatusCode::FORBIDDEN, "blocked").into_response();
     }
     if let Some(act) = action {
@@ -32,11 +50,20 @@ pub async fn chat(State(state): State<AppState>, Json(body): Json<ChatRequest>)
             rewritten = rewritten.clone();
         }
     }
-    let fragment_texts: Vec<String> = fragments.iter().map(|(_, t)| t.clone()).collect();
-    let fragment_ids: Vec<Uuid> = fragments.iter().map(|(id, _)| *id).collect();
-    match llm_client::chat(&state, &rewritten, &fragment_texts).await {
+    let fragment_texts: Vec<String> = fragments.iter().map(|(_, t, _)| t.clone()).collect();
+    let fragment_ids: Vec<Uuid> = fragments.iter().map(|(id, _, _)| *id).collect();
+    match llm_client::chat(
+        &state,
+        org_id,
+        &rewritten,
+        &fragment_texts,
+        body.provider.as_deref(),
+        body.model.as_deref(),
+    )
+    .await
+    {
         Ok(resp) => {
-            let _ = audit::log_chat(&state, "00000000-0000-0000-0000-000000000000", &body.prompt, &resp, action.as_deref().unwrap_or("pass"), resp.len() as i32, None, &fragment_ids, Some(&json!({"rewritten": rewritten != body.prompt}))).await;
+            let _ = audit::log_chat(&state, &org_id_str, &body.prompt, &resp, action.as_deref().unwrap_or("pass"), resp.len() as i32, top_score, &fragment_ids, Some(&json!({"rewritten": rewritten != body.prompt}))).await;
             Json(ChatResponse { response: resp }).into_response()
         },
         Err(err) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
@@ -57,9 +84,20 @@ pub async fn upload_doc(State(state): State<AppState>, bytes: axum::body::Bytes)
     }
 }
 
+pub async fn metrics() -> impl IntoResponse {
+    crate::metrics::encode_text()
+}
+
 pub async fn get_doc(State(state): State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
     match data_room::get_doc(&state, &id).await {
-        Ok(bytes) => (axum::http::StatusCode::OK, axum::body::Bytes::from(bytes)).into_response(),
+        Ok(data_room::DocLocation::Redirect(url)) => (
+            axum::http::StatusCode::FOUND,
+            [(axum::http::header::LOCATION, url.to_string())],
+        )
+            .into_response(),
+        Ok(data_room::DocLocation::Bytes(bytes)) => {
+            (axum::http::StatusCode::OK, axum::body::Bytes::from(bytes)).into_response()
+        }
         Err(err) => (axum::http::StatusCode::NOT_FOUND, err.to_string()).into_response(),
     }
 }
@@ -1,28 +1,90 @@
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
+use async_trait::async_trait;
 use tokio::fs;
+use url::Url;
+
+use crate::config::Settings;
+
+mod s3;
+pub use s3::S3Backend;
+
+/// Pluggable object storage for uploaded documents.
+///
+/// Swapped via `Settings` so the gateway can run against the local
+/// filesystem in dev and an S3-compatible bucket in production without
+/// touching call sites.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    async fn save(&self, bytes: &[u8]) -> Result<String>;
+    async fn get(&self, id: &str) -> Result<Vec<u8>>;
+    async fn list(&self) -> Result<Vec<String>>;
+    /// Returns a time-limited URL that fetches `id` directly, bypassing the
+    /// gateway. Backends with no direct-fetch story (e.g. the filesystem)
+    /// return `None`.
+    async fn presign_get(&self, id: &str, ttl: Duration) -> Result<Option<Url>>;
+}
 
 #[derive(Clone)]
 pub struct Storage {
-    root: PathBuf,
+    backend: Arc<dyn StorageBackend>,
 }
 
 impl Storage {
-    pub async fn new(root: &str) -> Result<Self> {
+    pub async fn new(settings: &Settings) -> Result<Self> {
+        let backend: Arc<dyn StorageBackend> = match &settings.s3_bucket {
+            Some(_) => Arc::new(S3Backend::new(settings)?),
+            None => Arc::new(FsBackend::new(&settings.storage_path).await?),
+        };
+        Ok(Self { backend })
+    }
+
+    pub async fn save(&self, bytes: &[u8]) -> Result<String> {
+        self.backend.save(bytes).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Vec<u8>> {
+        self.backend.get(id).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<String>> {
+        self.backend.list().await
+    }
+
+    pub async fn presign_get(&self, id: &str, ttl: Duration) -> Result<Option<Url>> {
+        self.backend.presign_get(id, ttl).await
+    }
+}
+
+struct FsBackend {
+    root: PathBuf,
+}
+
+impl FsBackend {
+    async fn new(root: &str) -> Result<Self> {
         let path = PathBuf::from(root);
         fs::create_dir_all(&path).await?;
         Ok(Self { root: path })
     }
+}
 
-    pub async fn save(&self, bytes: &[u8]) -> Result<String> {
+#[async_trait]
+impl StorageBackend for FsBackend {
+    async fn save(&self, bytes: &[u8]) -> Result<String> {
         let id = uuid::Uuid::new_v4().to_string();
         let file = self.root.join(&id);
         fs::write(&file, bytes).await?;
         Ok(id)
     }
 
-    pub async fn list(&self) -> Result<Vec<String>> {
+    async fn get(&self, id: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.root.join(id)).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<String>> {
         let mut out = Vec::new();
         let mut entries = fs::read_dir(&self.root).await?;
         while let Some(e) = entries.next_entry().await? {
@@ -34,4 +96,8 @@ impl Storage {
         }
         Ok(out)
     }
+
+    async fn presign_get(&self, _id: &str, _ttl: Duration) -> Result<Option<Url>> {
+        Ok(None)
+    }
 }
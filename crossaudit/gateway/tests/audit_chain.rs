@@ -0,0 +1,54 @@
+use crossaudit_gateway::{audit, config::Settings, init_state};
+use uuid::Uuid;
+
+fn test_settings() -> Settings {
+    let db_url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "postgresql://localhost/postgres".into());
+    Settings {
+        server_addr: "127.0.0.1:0".into(),
+        database_url: db_url,
+        openai_api_key: String::new(),
+        admin_token: String::new(),
+        storage_path: "./tmp-test-storage".into(),
+        local_model_path: None,
+        s3_endpoint: None,
+        s3_bucket: None,
+        s3_region: None,
+        s3_access_key: None,
+        s3_secret_key: None,
+        vector_distance_metric: "l2".into(),
+        hnsw_ef_search: None,
+        ivfflat_probes: None,
+        vector_min_score: None,
+    }
+}
+
+#[tokio::test]
+async fn tampered_entry_breaks_the_chain() {
+    let state = init_state(test_settings()).await.unwrap();
+    let org_id = Uuid::new_v4();
+    let org_str = org_id.to_string();
+
+    for i in 0..3 {
+        audit::log_chat(&state, &org_str, "prompt", &format!("response {i}"), "pass", 10, None, &[], None)
+            .await
+            .unwrap();
+    }
+
+    let report = audit::verify_chain(&state.pool, org_id).await.unwrap();
+    assert!(report.intact);
+    assert_eq!(report.entries_checked, 3);
+
+    let client = state.pool.get().await.unwrap();
+    client
+        .execute(
+            "UPDATE audit_ledger SET response = 'tampered' \
+             WHERE id = (SELECT id FROM audit_ledger WHERE org_id = $1 ORDER BY id ASC LIMIT 1)",
+            &[&org_id],
+        )
+        .await
+        .unwrap();
+
+    let report = audit::verify_chain(&state.pool, org_id).await.unwrap();
+    assert!(!report.intact);
+    assert!(report.broken_at.is_some());
+}
@@ -1,10 +1,14 @@
-<<<<<<< codex/replace-dummy-unit-tests-and-update-docs
+use axum::body::Bytes;
+use crossaudit_gateway::data_room::search;
 use crossaudit_gateway::storage::Storage;
+use crossaudit_gateway::{config::Settings, init_state};
 
 #[tokio::test]
 async fn save_and_list_documents() {
     let dir = tempfile::tempdir().unwrap();
-    let storage = Storage::new(dir.path().to_str().unwrap()).await.unwrap();
+    let mut settings = test_settings();
+    settings.storage_path = dir.path().to_str().unwrap().into();
+    let storage = Storage::new(&settings).await.unwrap();
 
     let id1 = storage.save(b"one").await.unwrap();
     let id2 = storage.save(b"two").await.unwrap();
@@ -15,26 +19,36 @@ async fn save_and_list_documents() {
     assert_eq!(list.len(), 2);
     assert!(list.contains(&id1));
     assert!(list.contains(&id2));
-=======
-use crossaudit_gateway::{config::Settings, init_state};
-use crossaudit_gateway::data_room::{save_doc, search};
-use axum::body::Bytes;
+}
 
-#[tokio::test]
-async fn upload_and_search() {
+fn test_settings() -> Settings {
     let db_url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "postgresql://localhost/postgres".into());
-    let settings = Settings {
+    Settings {
         server_addr: "127.0.0.1:0".into(),
         database_url: db_url,
         openai_api_key: String::new(),
+        admin_token: String::new(),
         storage_path: "./tmp-test-storage".into(),
-    };
-    let state = init_state(settings.clone()).await.unwrap();
+        local_model_path: None,
+        s3_endpoint: None,
+        s3_bucket: None,
+        s3_region: None,
+        s3_access_key: None,
+        s3_secret_key: None,
+        vector_distance_metric: "l2".into(),
+        hnsw_ef_search: None,
+        ivfflat_probes: None,
+        vector_min_score: None,
+    }
+}
+
+#[tokio::test]
+async fn upload_and_search() {
+    let state = init_state(test_settings()).await.unwrap();
 
     let bytes = include_bytes!("fixtures/hello.pdf");
-    save_doc(&state, Bytes::from_static(bytes)).await.unwrap();
+    crossaudit_gateway::data_room::save_doc(&state, Bytes::from_static(bytes)).await.unwrap();
 
     let res = search(&state, "Hello", 5).await.unwrap();
     assert!(!res.is_empty());
->>>>>>> main
 }
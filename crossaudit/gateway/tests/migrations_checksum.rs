@@ -0,0 +1,33 @@
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use tokio_postgres::{Config as PgConfig, NoTls};
+
+use crossaudit_gateway::migrations;
+
+fn test_pool() -> Pool {
+    let db_url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "postgresql://localhost/postgres".into());
+    let pg_cfg: PgConfig = db_url.parse().unwrap();
+    let mgr_config = ManagerConfig { recycling_method: RecyclingMethod::Fast };
+    let mgr = Manager::from_config(pg_cfg, NoTls, mgr_config);
+    Pool::builder(mgr).max_size(4).build().unwrap()
+}
+
+/// A migration file edited after it was applied must be rejected rather
+/// than silently re-applied: `run` records a checksum per version the
+/// first time it runs a file, and should `bail!` the next time it sees
+/// that version with different contents on disk instead of re-running
+/// drifted DDL.
+#[tokio::test]
+async fn drifted_migration_is_rejected() {
+    let pool = test_pool();
+    let dir = tempfile::tempdir().unwrap();
+    let version = "9001_test_drift";
+    let path = dir.path().join(format!("{version}.sql"));
+
+    std::fs::write(&path, "SELECT 1;").unwrap();
+    migrations::run(&pool, dir.path().to_str().unwrap()).await.unwrap();
+    assert_eq!(migrations::current_version(&pool).await.unwrap().as_deref(), Some(version));
+
+    std::fs::write(&path, "SELECT 2;").unwrap();
+    let err = migrations::run(&pool, dir.path().to_str().unwrap()).await.unwrap_err();
+    assert!(err.to_string().contains("drifted"));
+}
@@ -1,6 +1,9 @@
 use tokio::time::{sleep, Duration};
 use deadpool_postgres::{Manager, Pool, RecyclingMethod, ManagerConfig};
 use tokio_postgres::{NoTls, Config as PgConfig};
+use uuid::Uuid;
+
+use crossaudit_gateway::{audit, migrations};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -10,14 +13,26 @@ async fn main() -> anyhow::Result<()> {
     let mgr = Manager::from_config(pg_cfg, NoTls, mgr_config);
     let pool = Pool::builder(mgr).max_size(4).build().unwrap();
 
+    migrations::run(&pool, "./sql").await?;
+
     loop {
         let client = pool.get().await?;
-        client
-            .execute(
-                "UPDATE audit_ledger SET trace = coalesce(trace, '{}'::jsonb) || jsonb_build_object('sealed', true) WHERE trace->>'sealed' IS NULL",
-                &[],
-            )
-            .await?;
+        let rows = client.query("SELECT DISTINCT org_id FROM audit_ledger", &[]).await?;
+        for row in &rows {
+            let org_id: Uuid = row.get(0);
+            let report = audit::verify_chain(&pool, org_id).await?;
+            if report.intact {
+                audit::checkpoint(&pool, org_id).await?;
+            } else {
+                eprintln!("audit ledger tamper detected for org {org_id}: {report:?}");
+                client
+                    .execute(
+                        "INSERT INTO tamper_reports (org_id, broken_at, detected_at) VALUES ($1,$2,now())",
+                        &[&org_id, &report.broken_at],
+                    )
+                    .await?;
+            }
+        }
         sleep(Duration::from_secs(3600)).await;
     }
 }